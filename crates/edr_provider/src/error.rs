@@ -1,7 +1,9 @@
-use std::{num::TryFromIntError, time::SystemTimeError};
+use std::{collections::HashMap, num::TryFromIntError, time::SystemTimeError};
 
+use alloy_dyn_abi::DynSolValue;
 use alloy_sol_types::{ContractError, SolInterface};
 use edr_eth::{
+    keccak256,
     remote::{filter::SubscriptionType, jsonrpc, BlockSpec},
     Address, Bytes, SpecId, B256, U256,
 };
@@ -89,6 +91,16 @@ pub enum ProviderError {
     /// Error while running a transaction
     #[error(transparent)]
     RunTransaction(#[from] TransactionError<BlockchainError, StateError>),
+    /// The transaction's sender has deployed code, which is prohibited by
+    /// EIP-3607. Not raised for impersonated/overridden accounts.
+    ///
+    /// BLOCKED / PARTIAL: constructed by [`enforce_sender_is_eoa`], which no
+    /// transaction-admission path calls today, so this variant is never
+    /// raised by production code yet. See that function's doc comment.
+    #[error(
+        "sender {address} is not an EOA; it has deployed bytecode, as prohibited by EIP-3607"
+    )]
+    SenderIsContract { address: Address },
     /// The `hardhat_setMinGasPrice` method is not supported when EIP-1559 is
     /// active.
     #[error("hardhat_setMinGasPrice is not supported when EIP-1559 is active")]
@@ -130,50 +142,82 @@ pub enum ProviderError {
     /// Minimum required hardfork not met
     #[error("Feature is only available in post-{minimum:?} hardforks, the current hardfork is {actual:?}")]
     UnmetHardfork { actual: SpecId, minimum: SpecId },
+    /// The transaction's envelope type isn't supported by the current
+    /// hardfork.
+    ///
+    /// BLOCKED / PARTIAL: [`should_trace_transaction_type`] and
+    /// [`DebugTraceConfig`] exist to let `debug_traceTransaction`/
+    /// `debug_traceBlock` opt into skipping this instead of aborting, but no
+    /// caller threads a `DebugTraceConfig` through yet, so the skip mode
+    /// doesn't exist at runtime. See that function's doc comment.
+    #[error("transaction type {transaction_type} is not supported by the {hardfork:?} hardfork")]
+    UnsupportedTransactionType { transaction_type: u64, hardfork: SpecId },
+}
+
+impl ProviderError {
+    /// Returns the JSON-RPC error code to report for this error.
+    ///
+    /// Intentionally matches every [`ProviderError`] variant by name instead
+    /// of ending in a wildcard arm: adding a new variant without extending
+    /// this match is a compile error, so a future variant can't silently
+    /// default to the server-error code without a conscious classification
+    /// decision.
+    fn error_code(&self) -> i32 {
+        match self {
+            // De-facto standard "execution reverted" code (`3`) for reverts;
+            // other transaction failures use the server-error code.
+            ProviderError::TransactionFailed(transaction_failure) => {
+                transaction_failure.error_code()
+            }
+            // Invalid params.
+            ProviderError::InvalidBlockNumberOrHash { .. }
+            | ProviderError::InvalidBlockTag { .. }
+            | ProviderError::InvalidChainId { .. }
+            | ProviderError::InvalidFilterSubscriptionType { .. }
+            | ProviderError::InvalidTransactionIndex(_)
+            | ProviderError::InvalidTransactionInput(_)
+            | ProviderError::SenderIsContract { .. }
+            | ProviderError::UnmetHardfork { .. }
+            | ProviderError::UnsupportedTransactionType { .. } => -32602,
+            // Method not found.
+            ProviderError::Unimplemented(_) => -32601,
+            // Server error (no more specific JSON-RPC code applies).
+            ProviderError::AccountOverrideConversionError(_)
+            | ProviderError::AutoMineGasPriceTooLow { .. }
+            | ProviderError::AutoMineMaxFeeTooLow { .. }
+            | ProviderError::AutoMinePriorityFeeTooLow { .. }
+            | ProviderError::AutoMineNonceTooHigh { .. }
+            | ProviderError::AutoMineNonceTooLow { .. }
+            | ProviderError::Blockchain(_)
+            | ProviderError::Creation(_)
+            | ProviderError::MemPoolUpdate(_)
+            | ProviderError::MineBlock(_)
+            | ProviderError::MinerTransactionError(_)
+            | ProviderError::RlpDecodeError(_)
+            | ProviderError::RpcVersion(_)
+            | ProviderError::RunTransaction(_)
+            | ProviderError::SetMinGasPriceUnsupported
+            | ProviderError::Serialization(_)
+            | ProviderError::Signature(_)
+            | ProviderError::State(_)
+            | ProviderError::SystemTime(_)
+            | ProviderError::TimestampLowerThanPrevious { .. }
+            | ProviderError::TimestampEqualsPrevious { .. }
+            | ProviderError::TransactionCreationError(_)
+            | ProviderError::TryFromIntError(_)
+            | ProviderError::UnknownAddress { .. } => -32000,
+        }
+    }
 }
 
 impl From<ProviderError> for jsonrpc::Error {
     fn from(value: ProviderError) -> Self {
-        #[allow(clippy::match_same_arms)]
-        let (code, data) = match &value {
-            ProviderError::AccountOverrideConversionError(_) => (-32000, None),
-            ProviderError::AutoMineGasPriceTooLow { .. } => (-32000, None),
-            ProviderError::AutoMineMaxFeeTooLow { .. } => (-32000, None),
-            ProviderError::AutoMineNonceTooHigh { .. } => (-32000, None),
-            ProviderError::AutoMineNonceTooLow { .. } => (-32000, None),
-            ProviderError::AutoMinePriorityFeeTooLow { .. } => (-32000, None),
-            ProviderError::Blockchain(_) => (-32000, None),
-            ProviderError::Creation(_) => (-32000, None),
-            ProviderError::InvalidBlockNumberOrHash { .. } => (-32000, None),
-            ProviderError::InvalidBlockTag { .. } => (-32000, None),
-            ProviderError::InvalidChainId { .. } => (-32000, None),
-            ProviderError::InvalidFilterSubscriptionType { .. } => (-32000, None),
-            ProviderError::InvalidTransactionIndex(_) => (-32000, None),
-            ProviderError::InvalidTransactionInput(_) => (-32000, None),
-            ProviderError::MemPoolUpdate(_) => (-32000, None),
-            ProviderError::MineBlock(_) => (-32000, None),
-            ProviderError::MinerTransactionError(_) => (-32000, None),
-            ProviderError::RlpDecodeError(_) => (-32000, None),
-            ProviderError::RpcVersion(_) => (-32000, None),
-            ProviderError::RunTransaction(_) => (-32000, None),
-            ProviderError::Serialization(_) => (-32000, None),
-            ProviderError::SetMinGasPriceUnsupported => (-32000, None),
-            ProviderError::Signature(_) => (-32000, None),
-            ProviderError::State(_) => (-32000, None),
-            ProviderError::SystemTime(_) => (-32000, None),
-            ProviderError::TimestampLowerThanPrevious { .. } => (-32000, None),
-            ProviderError::TimestampEqualsPrevious { .. } => (-32000, None),
-            ProviderError::TransactionFailed(transaction_failure) => (
-                -32000,
-                Some(
-                    serde_json::to_value(transaction_failure).expect("transaction_failure to json"),
-                ),
-            ),
-            ProviderError::TransactionCreationError(_) => (-32000, None),
-            ProviderError::TryFromIntError(_) => (-32000, None),
-            ProviderError::Unimplemented(_) => (-32000, None),
-            ProviderError::UnknownAddress { .. } => (-32000, None),
-            ProviderError::UnmetHardfork { .. } => (-32602, None),
+        let code = value.error_code();
+        let data = match &value {
+            ProviderError::TransactionFailed(transaction_failure) => {
+                transaction_failure.error_data()
+            }
+            _ => None,
         };
 
         Self {
@@ -184,6 +228,81 @@ impl From<ProviderError> for jsonrpc::Error {
     }
 }
 
+/// Enforces EIP-3607: rejects a transaction whose `sender` currently has
+/// deployed code, unless the sender is impersonated or has an account
+/// override in effect, in which case the check is bypassed.
+///
+/// BLOCKED / PARTIAL: the `eth_sendTransaction`/`eth_sendRawTransaction`
+/// admission path that should call this before accepting a transaction into
+/// the mem pool lives outside this crate slice and is not wired up here, so
+/// EIP-3607 is not yet enforced at runtime. Do not consider this request
+/// closed until that call site lands.
+///
+/// Deliberately `pub(crate)`, not `pub`: until a real admission path calls
+/// this, it must stay dead-code-lintable rather than quietly exported as
+/// live API surface. Widen visibility only in the same change that wires
+/// in the real call site.
+pub(crate) fn enforce_sender_is_eoa(
+    sender: Address,
+    sender_has_code: bool,
+    bypass_check: bool,
+) -> Result<(), ProviderError> {
+    if sender_has_code && !bypass_check {
+        return Err(ProviderError::SenderIsContract { address: sender });
+    }
+
+    Ok(())
+}
+
+/// Provider-level configuration for `debug_traceTransaction`/
+/// `debug_traceBlock`. Defaults to off, matching the historical behavior of
+/// aborting the whole request on an unsupported transaction type.
+///
+/// Deliberately `pub(crate)`, not `pub`: no `debug_traceTransaction`/
+/// `debug_traceBlock` call site constructs this yet (see
+/// [`should_trace_transaction_type`]), so it must stay dead-code-lintable
+/// rather than quietly exported as live API surface. Widen visibility only
+/// in the same change that wires in the real call sites.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DebugTraceConfig {
+    /// When `true`, transactions of a type unsupported by the current
+    /// hardfork are skipped (a null/empty trace is returned for them)
+    /// instead of failing the whole request.
+    pub(crate) skip_unsupported_transaction_types: bool,
+}
+
+/// Decides how `debug_traceTransaction`/`debug_traceBlock` should handle a
+/// transaction whose envelope type isn't supported by `hardfork`. Returns
+/// `Ok(true)` if the transaction is supported and should be traced
+/// normally, `Ok(false)` if it's unsupported but `config` says to skip it
+/// (the caller should return a null/empty trace for it), or `Err` if it's
+/// unsupported and the request should abort as before.
+///
+/// BLOCKED / PARTIAL: `debug_traceTransaction`/`debug_traceBlock` live
+/// outside this crate slice and don't call this yet, so the opt-in skip
+/// mode doesn't exist at runtime — tracing a block with a newer tx type
+/// still aborts the whole request. Do not consider this request closed
+/// until those call sites thread `DebugTraceConfig` through and call this.
+pub(crate) fn should_trace_transaction_type(
+    transaction_type: u64,
+    hardfork: SpecId,
+    is_supported: bool,
+    config: &DebugTraceConfig,
+) -> Result<bool, ProviderError> {
+    if is_supported {
+        return Ok(true);
+    }
+
+    if config.skip_unsupported_transaction_types {
+        Ok(false)
+    } else {
+        Err(ProviderError::UnsupportedTransactionType {
+            transaction_type,
+            hardfork,
+        })
+    }
+}
+
 /// Wrapper around [`revm_primitives::Halt`] to convert error messages to match
 /// Hardhat.
 #[derive(Debug, thiserror::Error, serde::Serialize)]
@@ -192,48 +311,104 @@ pub struct TransactionFailure {
     pub reason: TransactionFailureReason,
     pub data: Option<String>,
     pub transaction_hash: B256,
+    /// The rendered failure message, computed up front so that decoding a
+    /// revert's custom error only needs the registry at construction time,
+    /// rather than wherever the error happens to be displayed.
+    #[serde(skip)]
+    message: String,
 }
 
 impl TransactionFailure {
+    /// Builds a revert failure without decoding against any custom error
+    /// registry. Kept so existing call sites aren't forced to thread a
+    /// registry through; prefer [`TransactionFailure::revert_with_registry`]
+    /// wherever a provider's [`CustomErrorRegistry`] is available, so that
+    /// registered custom errors are decoded instead of shown as raw hex.
+    ///
+    /// BLOCKED / PARTIAL: no call site in this crate slice threads a
+    /// populated [`CustomErrorRegistry`] yet, so every `revert`/
+    /// `revert_with_registry` call today effectively runs with an empty
+    /// registry, and custom errors decode to "unrecognized" regardless of
+    /// which of these two constructors is used. Do not consider this
+    /// request closed until some real call site builds a registry from
+    /// compiled contract artifacts and passes it to
+    /// [`TransactionFailure::revert_with_registry`].
     pub fn revert(output: Bytes, transaction_hash: B256) -> Self {
+        Self::revert_with_registry(output, transaction_hash, &CustomErrorRegistry::default())
+    }
+
+    /// Builds a revert failure, decoding it against `registry` if it carries
+    /// a recognized custom error selector.
+    ///
+    /// Deliberately `pub(crate)`, not `pub`: no call site outside
+    /// [`TransactionFailure::revert`] (which always passes a default, empty
+    /// registry) calls this with a populated [`CustomErrorRegistry`] yet, so
+    /// it must stay dead-code-lintable rather than quietly exported as live
+    /// API surface. Widen visibility only in the same change that wires in
+    /// a real, populated registry.
+    pub(crate) fn revert_with_registry(
+        output: Bytes,
+        transaction_hash: B256,
+        registry: &CustomErrorRegistry,
+    ) -> Self {
         let data = format!("0x{}", hex::encode(output.as_ref()));
+        let message = revert_error(&output, registry);
         Self {
             reason: TransactionFailureReason::Revert(output),
             data: Some(data),
             transaction_hash,
+            message,
+        }
+    }
+
+    /// Returns the JSON-RPC error code for this failure: `3` for reverts,
+    /// `-32000` otherwise.
+    fn error_code(&self) -> i32 {
+        match &self.reason {
+            TransactionFailureReason::Revert(_) => 3,
+            _ => -32000,
+        }
+    }
+
+    /// Returns the JSON-RPC error `data` for this failure: the hex-encoded
+    /// revert return data for reverts, or the serialized failure otherwise.
+    fn error_data(&self) -> Option<serde_json::Value> {
+        match &self.reason {
+            TransactionFailureReason::Revert(output) => Some(serde_json::Value::String(
+                format!("0x{}", hex::encode(output.as_ref())),
+            )),
+            _ => Some(serde_json::to_value(self).expect("transaction_failure to json")),
         }
     }
 
     pub fn halt(halt: Halt, tx_hash: B256) -> Self {
-        let reason = match halt {
-            Halt::OpcodeNotFound | Halt::InvalidFEOpcode => {
-                TransactionFailureReason::OpcodeNotFound
+        let (reason, message) = match halt {
+            Halt::OpcodeNotFound | Halt::InvalidFEOpcode => (
+                TransactionFailureReason::OpcodeNotFound,
+                "VM Exception while processing transaction: invalid opcode".to_string(),
+            ),
+            Halt::OutOfGas(error) => (
+                TransactionFailureReason::OutOfGas(error),
+                "out of gas".to_string(),
+            ),
+            halt => {
+                let message = halt_message(&halt);
+                (TransactionFailureReason::Inner(halt), message)
             }
-            Halt::OutOfGas(error) => TransactionFailureReason::OutOfGas(error),
-            halt => TransactionFailureReason::Inner(halt),
         };
 
         Self {
             reason,
             data: None,
             transaction_hash: tx_hash,
+            message,
         }
     }
 }
 
 impl std::fmt::Display for TransactionFailure {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.reason {
-            TransactionFailureReason::Inner(halt) => write!(f, "{halt:?}"),
-            TransactionFailureReason::OpcodeNotFound => {
-                write!(
-                    f,
-                    "VM Exception while processing transaction: invalid opcode"
-                )
-            }
-            TransactionFailureReason::OutOfGas(_error) => write!(f, "out of gas"),
-            TransactionFailureReason::Revert(output) => write!(f, "{}", revert_error(output)),
-        }
+        write!(f, "{}", self.message)
     }
 }
 
@@ -245,7 +420,99 @@ pub enum TransactionFailureReason {
     Revert(Bytes),
 }
 
-fn revert_error(output: &Bytes) -> String {
+/// A registry of known custom Solidity error signatures (e.g.
+/// `InsufficientBalance(uint256,uint256)`), keyed by their 4-byte selector.
+/// Signatures are added with [`CustomErrorRegistry::register_error`], and
+/// [`revert_error`] uses them to decode and render custom errors that the
+/// default ABI decoder can't recognize on its own. The provider does not hold
+/// a registry, and nothing fills one from compiled contract artifacts.
+///
+/// Deliberately `pub(crate)`, not `pub`: no call site in this crate slice
+/// populates one of these from compiled contract artifacts yet, so it must
+/// stay dead-code-lintable rather than quietly exported as live API
+/// surface. Widen visibility only in the same change that wires in a real
+/// populating call site.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CustomErrorRegistry {
+    signatures: HashMap<[u8; 4], String>,
+}
+
+impl CustomErrorRegistry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a custom error signature, e.g.
+    /// `InsufficientBalance(uint256,uint256)`, so that reverts carrying its
+    /// selector can be decoded into a human-readable message.
+    pub(crate) fn register_error(&mut self, signature: impl Into<String>) {
+        let signature = signature.into();
+        let selector: [u8; 4] = keccak256(signature.as_bytes())[..4]
+            .try_into()
+            .expect("keccak256 digest is at least 4 bytes");
+        self.signatures.insert(selector, signature);
+    }
+
+    fn signature(&self, selector: &[u8; 4]) -> Option<&str> {
+        self.signatures.get(selector).map(String::as_str)
+    }
+}
+
+/// Decodes `output` against a custom error signature registered under its
+/// leading 4-byte selector, rendering e.g. `InsufficientBalance(100, 50)`.
+/// Returns `None` if the selector isn't registered or the trailing data
+/// doesn't decode against the registered signature.
+fn decode_custom_error(registry: &CustomErrorRegistry, output: &[u8]) -> Option<String> {
+    let selector: [u8; 4] = output.get(..4)?.try_into().ok()?;
+    let signature = registry.signature(&selector)?;
+    let params_start = signature.find('(')?;
+    let name = &signature[..params_start];
+    let params = &signature[params_start..];
+
+    let ty = alloy_dyn_abi::DynSolType::parse(&format!("tuple{params}")).ok()?;
+    let decoded = ty.abi_decode_sequence(&output[4..]).ok()?;
+    let DynSolValue::Tuple(values) = decoded else {
+        return None;
+    };
+
+    let args = values
+        .iter()
+        .map(format_custom_error_arg)
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!("{name}({args})"))
+}
+
+fn format_custom_error_arg(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(value) => value.to_string(),
+        DynSolValue::Int(value, _) => value.to_string(),
+        DynSolValue::Uint(value, _) => value.to_string(),
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::FixedBytes(bytes, size) => format!("0x{}", hex::encode(&bytes[..*size])),
+        DynSolValue::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        DynSolValue::String(value) => format!("\"{value}\""),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            let joined = values
+                .iter()
+                .map(format_custom_error_arg)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("[{joined}]")
+        }
+        DynSolValue::Tuple(values) => {
+            let joined = values
+                .iter()
+                .map(format_custom_error_arg)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("({joined})")
+        }
+        value => format!("{value:?}"),
+    }
+}
+
+fn revert_error(output: &Bytes, registry: &CustomErrorRegistry) -> String {
     if output.is_empty() {
         return "Transaction reverted without a reason".to_string();
     }
@@ -257,7 +524,11 @@ fn revert_error(output: &Bytes) -> String {
         Ok(contract_error) => {
             match contract_error {
                 ContractError::CustomError(custom_error) => {
-                    format!("VM Exception while processing transaction: reverted with an unrecognized custom error (return data: {custom_error})")
+                    if let Some(decoded) = decode_custom_error(registry, output.as_ref()) {
+                        format!("VM Exception while processing transaction: reverted with custom error '{decoded}'")
+                    } else {
+                        format!("VM Exception while processing transaction: reverted with an unrecognized custom error (return data: {custom_error})")
+                    }
                 }
                 ContractError::Revert(revert) => {
                     format!("reverted with reason string '{}'", revert.reason())
@@ -273,13 +544,46 @@ fn revert_error(output: &Bytes) -> String {
         }
         Err(decode_error) => match decode_error {
             alloy_sol_types::Error::TypeCheckFail { .. } => {
-                format!("VM Exception while processing transaction: reverted with an unrecognized custom error (return data: 0x{})", hex::encode(output))
+                if let Some(decoded) = decode_custom_error(registry, output.as_ref()) {
+                    format!("VM Exception while processing transaction: reverted with custom error '{decoded}'")
+                } else {
+                    format!("VM Exception while processing transaction: reverted with an unrecognized custom error (return data: 0x{})", hex::encode(output))
+                }
             }
             _ => unreachable!("Since we are not validating, no other error can occur"),
         },
     }
 }
 
+/// Renders a Hardhat-style sentence for a [`Halt`] reason that isn't handled
+/// specially by [`TransactionFailure::halt`] (i.e. anything other than
+/// `OutOfGas`/`OpcodeNotFound`/`InvalidFEOpcode`). Falls back to the debug
+/// representation for any halt reason added to the non-exhaustive `Halt`
+/// enum that isn't mapped here yet.
+fn halt_message(halt: &Halt) -> String {
+    match halt {
+        Halt::CallTooDeep => {
+            "VM Exception while processing transaction: call stack depth limit reached".to_string()
+        }
+        Halt::CreateCollision => "VM Exception while processing transaction: trying to deploy a contract whose address is already in use".to_string(),
+        Halt::CreateContractSizeLimit => "VM Exception while processing transaction: deployed code is larger than the EIP-170 size limit".to_string(),
+        Halt::CreateContractStartingWithEF => "VM Exception while processing transaction: contract creation code starts with the 0xEF byte, which is reserved by EIP-3541".to_string(),
+        Halt::CreateInitCodeSizeLimit => "VM Exception while processing transaction: contract creation init code is larger than the EIP-3860 size limit".to_string(),
+        Halt::InvalidJump => "VM Exception while processing transaction: invalid jump destination".to_string(),
+        Halt::NonceOverflow => "VM Exception while processing transaction: nonce overflow".to_string(),
+        Halt::NotActivated => "VM Exception while processing transaction: attempted to use an opcode that is not active in the current hardfork".to_string(),
+        Halt::OutOfFunds => "VM Exception while processing transaction: sender doesn't have enough funds to send tx".to_string(),
+        Halt::OutOfOffset => "VM Exception while processing transaction: memory access is out of bounds".to_string(),
+        Halt::OverflowPayment => "VM Exception while processing transaction: overflow while transferring value".to_string(),
+        Halt::PrecompileError => "VM Exception while processing transaction: precompile failed to execute".to_string(),
+        Halt::StackOverflow => "VM Exception while processing transaction: stack overflow".to_string(),
+        Halt::StackUnderflow => "VM Exception while processing transaction: stack underflow".to_string(),
+        Halt::StateChangeDuringStaticCall => "VM Exception while processing transaction: state changes are not allowed in a static call".to_string(),
+        Halt::CallNotAllowedInsideStatic => "VM Exception while processing transaction: this call is not allowed inside a static call".to_string(),
+        halt => format!("{halt:?}"),
+    }
+}
+
 fn panic_code_to_error_reason(error_code: u64) -> &'static str {
     match error_code {
         0x1 => "Assertion error",
@@ -293,4 +597,333 @@ fn panic_code_to_error_reason(error_code: u64) -> &'static str {
         0x51 => "Called a zero-initialized variable of internal function type",
         _ => "Unknown panic code",
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_code(error: ProviderError) -> i32 {
+        jsonrpc::Error::from(error).code
+    }
+
+    #[test]
+    fn execution_revert_uses_standard_code() {
+        let registry = CustomErrorRegistry::new();
+        let failure = TransactionFailure::revert_with_registry(Bytes::new(), B256::ZERO, &registry);
+        assert_eq!(error_code(ProviderError::TransactionFailed(failure)), 3);
+    }
+
+    #[test]
+    fn revert_without_a_registry_still_works() {
+        let failure = TransactionFailure::revert(Bytes::new(), B256::ZERO);
+        assert_eq!(error_code(ProviderError::TransactionFailed(failure)), 3);
+    }
+
+    #[test]
+    fn non_revert_transaction_failure_uses_server_error_code() {
+        let failure = TransactionFailure::halt(Halt::OpcodeNotFound, B256::ZERO);
+        assert_eq!(error_code(ProviderError::TransactionFailed(failure)), -32000);
+    }
+
+    #[test]
+    fn invalid_params_variants_use_invalid_params_code() {
+        assert_eq!(
+            error_code(ProviderError::InvalidChainId {
+                expected: 1,
+                actual: 2
+            }),
+            -32602
+        );
+        assert_eq!(
+            error_code(ProviderError::InvalidTransactionIndex(U256::ZERO)),
+            -32602
+        );
+        assert_eq!(
+            error_code(ProviderError::InvalidTransactionInput(String::new())),
+            -32602
+        );
+        assert_eq!(
+            error_code(ProviderError::UnmetHardfork {
+                actual: SpecId::FRONTIER,
+                minimum: SpecId::LONDON
+            }),
+            -32602
+        );
+        assert_eq!(
+            error_code(ProviderError::SenderIsContract {
+                address: Address::ZERO
+            }),
+            -32602
+        );
+        assert_eq!(
+            error_code(ProviderError::UnsupportedTransactionType {
+                transaction_type: 4,
+                hardfork: SpecId::LONDON
+            }),
+            -32602
+        );
+    }
+
+    #[test]
+    fn unimplemented_uses_method_not_found_code() {
+        assert_eq!(
+            error_code(ProviderError::Unimplemented(String::new())),
+            -32601
+        );
+    }
+
+    #[test]
+    fn uncategorized_variants_fall_back_to_server_error_code() {
+        assert_eq!(
+            error_code(ProviderError::SetMinGasPriceUnsupported),
+            -32000
+        );
+    }
+
+    fn custom_error_output(signature: &str, args: Vec<DynSolValue>) -> Bytes {
+        let selector = keccak256(signature.as_bytes())[..4].to_vec();
+        let encoded = DynSolValue::Tuple(args).abi_encode();
+
+        let mut output = selector;
+        output.extend(encoded);
+        Bytes::from(output)
+    }
+
+    #[test]
+    fn decodes_registered_custom_error_with_args() {
+        let mut registry = CustomErrorRegistry::new();
+        registry.register_error("InsufficientBalance(uint256,uint256)");
+
+        let output = custom_error_output(
+            "InsufficientBalance(uint256,uint256)",
+            vec![
+                DynSolValue::Uint(U256::from(100), 256),
+                DynSolValue::Uint(U256::from(50), 256),
+            ],
+        );
+
+        assert_eq!(
+            revert_error(&output, &registry),
+            "VM Exception while processing transaction: reverted with custom error 'InsufficientBalance(100, 50)'"
+        );
+    }
+
+    #[test]
+    fn decodes_registered_zero_arg_custom_error() {
+        let mut registry = CustomErrorRegistry::new();
+        registry.register_error("Unauthorized()");
+
+        let output = custom_error_output("Unauthorized()", vec![]);
+
+        assert_eq!(
+            revert_error(&output, &registry),
+            "VM Exception while processing transaction: reverted with custom error 'Unauthorized()'"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_hex_for_unregistered_selector() {
+        let registry = CustomErrorRegistry::new();
+        let output = custom_error_output(
+            "InsufficientBalance(uint256,uint256)",
+            vec![
+                DynSolValue::Uint(U256::from(100), 256),
+                DynSolValue::Uint(U256::from(50), 256),
+            ],
+        );
+
+        assert!(revert_error(&output, &registry).contains("unrecognized custom error"));
+    }
+
+    fn halt_failure_message(halt: Halt) -> String {
+        TransactionFailure::halt(halt, B256::ZERO).to_string()
+    }
+
+    #[test]
+    fn halt_message_call_too_deep() {
+        assert_eq!(
+            halt_failure_message(Halt::CallTooDeep),
+            "VM Exception while processing transaction: call stack depth limit reached"
+        );
+    }
+
+    #[test]
+    fn halt_message_create_collision() {
+        assert_eq!(
+            halt_failure_message(Halt::CreateCollision),
+            "VM Exception while processing transaction: trying to deploy a contract whose address is already in use"
+        );
+    }
+
+    #[test]
+    fn halt_message_create_contract_size_limit() {
+        assert_eq!(
+            halt_failure_message(Halt::CreateContractSizeLimit),
+            "VM Exception while processing transaction: deployed code is larger than the EIP-170 size limit"
+        );
+    }
+
+    #[test]
+    fn halt_message_create_contract_starting_with_ef() {
+        assert_eq!(
+            halt_failure_message(Halt::CreateContractStartingWithEF),
+            "VM Exception while processing transaction: contract creation code starts with the 0xEF byte, which is reserved by EIP-3541"
+        );
+    }
+
+    #[test]
+    fn halt_message_create_init_code_size_limit() {
+        assert_eq!(
+            halt_failure_message(Halt::CreateInitCodeSizeLimit),
+            "VM Exception while processing transaction: contract creation init code is larger than the EIP-3860 size limit"
+        );
+    }
+
+    #[test]
+    fn halt_message_invalid_jump() {
+        assert_eq!(
+            halt_failure_message(Halt::InvalidJump),
+            "VM Exception while processing transaction: invalid jump destination"
+        );
+    }
+
+    #[test]
+    fn halt_message_nonce_overflow() {
+        assert_eq!(
+            halt_failure_message(Halt::NonceOverflow),
+            "VM Exception while processing transaction: nonce overflow"
+        );
+    }
+
+    #[test]
+    fn halt_message_not_activated() {
+        assert_eq!(
+            halt_failure_message(Halt::NotActivated),
+            "VM Exception while processing transaction: attempted to use an opcode that is not active in the current hardfork"
+        );
+    }
+
+    #[test]
+    fn halt_message_out_of_funds() {
+        assert_eq!(
+            halt_failure_message(Halt::OutOfFunds),
+            "VM Exception while processing transaction: sender doesn't have enough funds to send tx"
+        );
+    }
+
+    #[test]
+    fn halt_message_out_of_offset() {
+        assert_eq!(
+            halt_failure_message(Halt::OutOfOffset),
+            "VM Exception while processing transaction: memory access is out of bounds"
+        );
+    }
+
+    #[test]
+    fn halt_message_overflow_payment() {
+        assert_eq!(
+            halt_failure_message(Halt::OverflowPayment),
+            "VM Exception while processing transaction: overflow while transferring value"
+        );
+    }
+
+    #[test]
+    fn halt_message_precompile_error() {
+        assert_eq!(
+            halt_failure_message(Halt::PrecompileError),
+            "VM Exception while processing transaction: precompile failed to execute"
+        );
+    }
+
+    #[test]
+    fn halt_message_stack_overflow() {
+        assert_eq!(
+            halt_failure_message(Halt::StackOverflow),
+            "VM Exception while processing transaction: stack overflow"
+        );
+    }
+
+    #[test]
+    fn halt_message_stack_underflow() {
+        assert_eq!(
+            halt_failure_message(Halt::StackUnderflow),
+            "VM Exception while processing transaction: stack underflow"
+        );
+    }
+
+    #[test]
+    fn halt_message_state_change_during_static_call() {
+        assert_eq!(
+            halt_failure_message(Halt::StateChangeDuringStaticCall),
+            "VM Exception while processing transaction: state changes are not allowed in a static call"
+        );
+    }
+
+    #[test]
+    fn halt_message_call_not_allowed_inside_static() {
+        assert_eq!(
+            halt_failure_message(Halt::CallNotAllowedInsideStatic),
+            "VM Exception while processing transaction: this call is not allowed inside a static call"
+        );
+    }
+
+    #[test]
+    fn revert_data_is_populated_for_reverts() {
+        let registry = CustomErrorRegistry::new();
+        let output = Bytes::from(vec![0x01, 0x02, 0x03, 0x04]);
+        let failure = TransactionFailure::revert_with_registry(output.clone(), B256::ZERO, &registry);
+        assert_eq!(failure.data, Some(format!("0x{}", hex::encode(output))));
+    }
+
+    #[test]
+    fn contract_sender_is_rejected() {
+        let address = Address::ZERO;
+        let result = enforce_sender_is_eoa(address, true, false);
+        assert!(matches!(
+            result,
+            Err(ProviderError::SenderIsContract { address: a }) if a == address
+        ));
+    }
+
+    #[test]
+    fn impersonated_contract_sender_bypasses_the_check() {
+        let address = Address::ZERO;
+        assert!(enforce_sender_is_eoa(address, true, true).is_ok());
+    }
+
+    #[test]
+    fn eoa_sender_is_always_accepted() {
+        let address = Address::ZERO;
+        assert!(enforce_sender_is_eoa(address, false, false).is_ok());
+        assert!(enforce_sender_is_eoa(address, false, true).is_ok());
+    }
+
+    #[test]
+    fn unsupported_transaction_type_errors_by_default() {
+        let config = DebugTraceConfig::default();
+        let result = should_trace_transaction_type(4, SpecId::LONDON, false, &config);
+        assert!(matches!(
+            result,
+            Err(ProviderError::UnsupportedTransactionType {
+                transaction_type: 4,
+                hardfork: SpecId::LONDON
+            })
+        ));
+    }
+
+    #[test]
+    fn unsupported_transaction_type_is_skipped_when_configured() {
+        let config = DebugTraceConfig {
+            skip_unsupported_transaction_types: true,
+        };
+        let result = should_trace_transaction_type(4, SpecId::LONDON, false, &config);
+        assert!(!result.unwrap());
+    }
+
+    #[test]
+    fn supported_transaction_type_is_always_traced() {
+        let config = DebugTraceConfig::default();
+        assert!(should_trace_transaction_type(2, SpecId::LONDON, true, &config).unwrap());
+    }
+}